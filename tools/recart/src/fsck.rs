@@ -0,0 +1,796 @@
+use crate::server::{
+    distro_initramfs_name, distro_iso_name, distro_rootfs_name, hardlink_info, is_hex_64,
+    iso_key_files,
+};
+use crate::{find_iso_checksum_file, fmt_bytes, iso_input_key};
+use anyhow::{Context, Result};
+use distro_builder::artifact_store::ArtifactStore;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// A blob file found under `blobs/sha256/<prefix>/`.
+struct BlobFile {
+    sha256: String,
+    path: PathBuf,
+    size_bytes: u64,
+}
+
+/// The parts of an index entry fsck cross-checks.
+struct IndexRef {
+    input_key: String,
+    blob_sha256: String,
+}
+
+/// An index entry whose blob is missing or damaged.
+struct Dangling {
+    kind: String,
+    input_key: String,
+    blob_sha256: String,
+}
+
+/// Result of walking `blobs/sha256`.
+#[derive(Default)]
+struct BlobScan {
+    /// Blobs whose content matches their name.
+    verified: BTreeMap<String, BlobFile>,
+    /// Every validly named blob on disk, including corrupt, unreadable and misplaced ones.
+    on_disk: BTreeSet<String>,
+}
+
+/// An output file (plus the input key it was built from) that may still be
+/// hardlinked to, or byte-identical with, an orphaned blob.
+struct Candidate {
+    distro_dir: &'static str,
+    kind: &'static str,
+    input_key: String,
+    path: PathBuf,
+}
+
+#[derive(Default)]
+struct Report {
+    blobs_checked: u64,
+    blob_bytes: u64,
+    corrupt: Vec<(PathBuf, String)>,
+    unreadable: Vec<(PathBuf, String)>,
+    misplaced: Vec<PathBuf>,
+    stray: Vec<PathBuf>,
+    index_errors: Vec<(String, String)>,
+    index_entries: u64,
+    dangling: Vec<Dangling>,
+    broken_refs: Vec<Dangling>,
+    orphaned: Vec<BlobFile>,
+    rebuilt: u64,
+    unrecovered: u64,
+}
+
+impl Report {
+    fn is_clean(&self) -> bool {
+        self.corrupt.is_empty()
+            && self.unreadable.is_empty()
+            && self.misplaced.is_empty()
+            && self.index_errors.is_empty()
+            && self.dangling.is_empty()
+            && self.broken_refs.is_empty()
+            && self.orphaned.len() as u64 == self.rebuilt
+    }
+}
+
+/// Check the store for blob/index inconsistencies and, with `rebuild`, re-create
+/// index entries for orphaned blobs whose input key can be recovered from the
+/// distro output directories. Blobs are verified on up to `jobs` threads.
+///
+/// Returns `Ok(false)` when inconsistencies remain after the run.
+pub fn run(repo_root: &Path, store: &ArtifactStore, rebuild: bool, jobs: usize) -> Result<bool> {
+    let mut report = Report::default();
+
+    println!("== Checking blobs ==");
+    let blobs = scan_blobs(&store.root().join("blobs/sha256"), jobs, &mut report)?;
+
+    println!("== Checking index ==");
+    let list_kind = |kind: &str| -> Result<Vec<IndexRef>> {
+        Ok(store
+            .list_kind(kind)?
+            .into_iter()
+            .map(|e| IndexRef {
+                input_key: e.input_key,
+                blob_sha256: e.blob_sha256,
+            })
+            .collect())
+    };
+    let referenced = scan_index(&store.root().join("index"), list_kind, &blobs, &mut report)?;
+
+    report.orphaned = collect_orphans(blobs.verified, &referenced);
+
+    if rebuild && !report.orphaned.is_empty() {
+        println!("== Rebuilding index ==");
+        rebuild_index(repo_root, store, &mut report)?;
+    }
+
+    print_summary(&report, rebuild);
+    Ok(report.is_clean())
+}
+
+fn scan_blobs(blob_root: &Path, jobs: usize, report: &mut Report) -> Result<BlobScan> {
+    let mut blobs = BlobScan::default();
+    if !blob_root.exists() {
+        println!("  [SKIP] No blob directory at {}", blob_root.display());
+        return Ok(blobs);
+    }
+
+    let mut to_verify = vec![];
+    for prefix in read_dir_sorted(blob_root)? {
+        if !prefix.is_dir() {
+            report.stray.push(prefix);
+            continue;
+        }
+        let prefix_name = file_name_str(&prefix);
+
+        let paths = match read_dir_sorted(&prefix) {
+            Ok(paths) => paths,
+            Err(e) => {
+                report.unreadable.push((prefix, format!("{:#}", e)));
+                continue;
+            }
+        };
+
+        for path in paths {
+            let name = file_name_str(&path);
+            if !path.is_file() || !is_blob_name(&name) {
+                report.stray.push(path);
+                continue;
+            }
+            blobs.on_disk.insert(name.clone());
+            if name[..2] != prefix_name {
+                report.misplaced.push(path);
+                continue;
+            }
+            to_verify.push(path);
+        }
+    }
+
+    let started = Instant::now();
+    let workers = jobs.clamp(1, to_verify.len().max(1));
+    let hashes = sha256_files_parallel(&to_verify, workers);
+    let elapsed = started.elapsed();
+
+    for (path, hashed) in to_verify.into_iter().zip(hashes) {
+        let (actual, size_bytes) = match hashed {
+            Ok(hashed) => hashed,
+            Err(e) => {
+                report.unreadable.push((path, format!("{:#}", e)));
+                continue;
+            }
+        };
+        let name = file_name_str(&path);
+        report.blobs_checked += 1;
+        report.blob_bytes += size_bytes;
+        if actual != name {
+            report.corrupt.push((path, actual));
+            continue;
+        }
+
+        blobs.verified.insert(
+            name.clone(),
+            BlobFile {
+                sha256: name,
+                path,
+                size_bytes,
+            },
+        );
+    }
+
+    let secs = elapsed.as_secs_f64();
+    let rate = if secs > 0.0 {
+        (report.blob_bytes as f64 / secs) as u64
+    } else {
+        report.blob_bytes
+    };
+    println!(
+        "  Verified {} blob(s), {} in {:.1}s ({}/s, {} thread(s))",
+        report.blobs_checked,
+        fmt_bytes(report.blob_bytes),
+        secs,
+        fmt_bytes(rate),
+        workers
+    );
+    Ok(blobs)
+}
+
+fn scan_index(
+    index_root: &Path,
+    list_kind: impl Fn(&str) -> Result<Vec<IndexRef>>,
+    blobs: &BlobScan,
+    report: &mut Report,
+) -> Result<BTreeSet<String>> {
+    let mut referenced = BTreeSet::new();
+    if !index_root.exists() {
+        println!("  [WARN] No index directory at {}", index_root.display());
+        return Ok(referenced);
+    }
+
+    for kind_dir in read_dir_sorted(index_root)? {
+        if !kind_dir.is_dir() {
+            continue;
+        }
+        let kind = file_name_str(&kind_dir);
+
+        let entries = match list_kind(&kind) {
+            Ok(entries) => entries,
+            Err(e) => {
+                report.index_errors.push((kind, format!("{:#}", e)));
+                continue;
+            }
+        };
+
+        for e in entries {
+            report.index_entries += 1;
+            if !is_blob_name(&e.blob_sha256) {
+                report.index_errors.push((
+                    kind.clone(),
+                    format!(
+                        "key={} has malformed blob sha {:?}",
+                        e.input_key, e.blob_sha256
+                    ),
+                ));
+                continue;
+            }
+
+            if !blobs.verified.contains_key(&e.blob_sha256) {
+                let bad = Dangling {
+                    kind: kind.clone(),
+                    input_key: e.input_key.clone(),
+                    blob_sha256: e.blob_sha256.clone(),
+                };
+                if blobs.on_disk.contains(&e.blob_sha256) {
+                    report.broken_refs.push(bad);
+                } else {
+                    report.dangling.push(bad);
+                }
+            }
+            referenced.insert(e.blob_sha256);
+        }
+    }
+
+    println!("  Checked {} index entry(s)", report.index_entries);
+    Ok(referenced)
+}
+
+fn collect_orphans(
+    verified: BTreeMap<String, BlobFile>,
+    referenced: &BTreeSet<String>,
+) -> Vec<BlobFile> {
+    verified
+        .into_values()
+        .filter(|b| !referenced.contains(&b.sha256))
+        .collect()
+}
+
+fn rebuild_index(repo_root: &Path, store: &ArtifactStore, report: &mut Report) -> Result<()> {
+    let candidates = recovery_candidates(repo_root)?;
+    let mut recovered: BTreeSet<String> = BTreeSet::new();
+    let mut hashes = CandidateHashes::new();
+
+    for blob in &report.orphaned {
+        let Some(c) = candidates
+            .iter()
+            .find(|c| candidate_matches(c, blob, &mut hashes))
+        else {
+            continue;
+        };
+
+        match store.get(c.kind, &c.input_key) {
+            Ok(None) => {}
+            Ok(Some(_)) => {
+                println!(
+                    "  [SKIP] {} {} (index already has key {})",
+                    c.distro_dir, c.kind, c.input_key
+                );
+                continue;
+            }
+            Err(e) => {
+                eprintln!(
+                    "  [WARN] {} {} index lookup failed: {:#}",
+                    c.distro_dir, c.kind, e
+                );
+                continue;
+            }
+        }
+
+        let mut meta = BTreeMap::new();
+        meta.insert(
+            "distro".to_string(),
+            serde_json::Value::String(c.distro_dir.to_string()),
+        );
+        meta.insert(
+            "recovered_by".to_string(),
+            serde_json::Value::String("recart fsck --rebuild".to_string()),
+        );
+
+        match store.ingest_file_move_and_link(c.kind, &c.input_key, &c.path, meta) {
+            Ok(sha) if sha == blob.sha256 => {
+                println!(
+                    "  {} {:<17} key={} blob={}",
+                    c.distro_dir,
+                    c.kind,
+                    c.input_key,
+                    &sha[..16]
+                );
+                recovered.insert(blob.sha256.clone());
+                report.rebuilt += 1;
+            }
+            Ok(sha) => eprintln!(
+                "  [WARN] {} {} changed while rebuilding (now blob={})",
+                c.distro_dir,
+                c.kind,
+                &sha[..16]
+            ),
+            Err(e) => eprintln!(
+                "  [WARN] {} {} rebuild failed: {:#}",
+                c.distro_dir, c.kind, e
+            ),
+        }
+    }
+
+    report.unrecovered = report
+        .orphaned
+        .iter()
+        .filter(|b| !recovered.contains(&b.sha256))
+        .count() as u64;
+    Ok(())
+}
+
+/// Output files whose input key is still on disk. `kernel_payload` blobs are
+/// archives of the staging tree and cannot be matched back to a single file.
+fn recovery_candidates(repo_root: &Path) -> Result<Vec<Candidate>> {
+    let mut out = vec![];
+    for distro_dir in ["leviso", "AcornOS", "IuppiterOS"] {
+        let base_dir = repo_root.join(distro_dir);
+        if !base_dir.exists() {
+            continue;
+        }
+        let out_dir = distro_builder::artifact_store::central_output_dir_for_distro(&base_dir);
+
+        let mut files: Vec<(&'static str, PathBuf, Option<String>)> = vec![
+            (
+                "rootfs_erofs",
+                out_dir.join(distro_rootfs_name(distro_dir)),
+                read_key(&out_dir.join(".rootfs-inputs.hash"))?,
+            ),
+            (
+                "initramfs",
+                out_dir.join(distro_initramfs_name(distro_dir)),
+                read_key(&out_dir.join(".initramfs-inputs.hash"))?,
+            ),
+        ];
+        if distro_dir == "leviso" {
+            files.push((
+                "install_initramfs",
+                out_dir.join(distro_spec::levitate::INITRAMFS_INSTALLED_OUTPUT),
+                read_key(&out_dir.join(".install-initramfs-inputs.hash"))?,
+            ));
+        }
+
+        let iso = out_dir.join(distro_iso_name(distro_dir));
+        let iso_key = iso_input_key(&iso_key_files(distro_dir, &out_dir));
+        if let Some(checksum) = find_iso_checksum_file(&iso) {
+            files.push(("iso_checksum", checksum, iso_key.clone()));
+        }
+        files.push(("iso", iso, iso_key));
+
+        for (kind, path, key) in files {
+            if let (true, Some(input_key)) = (path.is_file(), key) {
+                out.push(Candidate {
+                    distro_dir,
+                    kind,
+                    input_key,
+                    path,
+                });
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn read_key(path: &Path) -> Result<Option<String>> {
+    distro_builder::artifact_store::read_input_key_file(path)
+}
+
+/// Candidate output hashes, computed at most once per path. `None` marks a
+/// candidate that could not be read (already warned about).
+type CandidateHashes = BTreeMap<PathBuf, Option<String>>;
+
+/// Outputs materialized from the store are hardlinks to their blob, so an inode
+/// match is conclusive. Otherwise fall back to hashing same-sized files.
+fn candidate_matches(c: &Candidate, blob: &BlobFile, hashes: &mut CandidateHashes) -> bool {
+    if let Some(known) = hashes.get(&c.path) {
+        return known.as_deref() == Some(blob.sha256.as_str());
+    }
+
+    let md = match std::fs::metadata(&c.path) {
+        Ok(md) => md,
+        Err(e) => {
+            eprintln!(
+                "  [WARN] {} {} candidate {} unreadable: {}",
+                c.distro_dir,
+                c.kind,
+                c.path.display(),
+                e
+            );
+            hashes.insert(c.path.clone(), None);
+            return false;
+        }
+    };
+    if md.len() != blob.size_bytes {
+        return false;
+    }
+    if hardlink_info(&c.path, &blob.path).is_some_and(|(same, _)| same) {
+        return true;
+    }
+
+    match sha256_file(&c.path) {
+        Ok((sha, _)) => {
+            let matches = sha == blob.sha256;
+            hashes.insert(c.path.clone(), Some(sha));
+            matches
+        }
+        Err(e) => {
+            eprintln!(
+                "  [WARN] {} {} candidate {} unreadable: {:#}",
+                c.distro_dir,
+                c.kind,
+                c.path.display(),
+                e
+            );
+            hashes.insert(c.path.clone(), None);
+            false
+        }
+    }
+}
+
+fn print_summary(report: &Report, rebuild: bool) {
+    println!();
+    println!("== fsck summary ==");
+    println!(
+        "  Blobs verified:     {} ({})",
+        report.blobs_checked,
+        fmt_bytes(report.blob_bytes)
+    );
+    println!("  Corrupt blobs:      {}", report.corrupt.len());
+    println!("  Unreadable blobs:   {}", report.unreadable.len());
+    println!("  Misplaced blobs:    {}", report.misplaced.len());
+    println!("  Stray files:        {}", report.stray.len());
+    println!("  Index entries:      {}", report.index_entries);
+    println!("  Unreadable kinds:   {}", report.index_errors.len());
+    println!("  Dangling entries:   {}", report.dangling.len());
+    println!("  Entries, bad blob:  {}", report.broken_refs.len());
+    println!("  Orphaned blobs:     {}", report.orphaned.len());
+    if rebuild {
+        println!("  Entries rebuilt:    {}", report.rebuilt);
+        println!("  Still orphaned:     {}", report.unrecovered);
+        if report.rebuilt > 0 {
+            println!("  Rebuilt outputs were moved into the store and replaced by a hardlink to their blob.");
+        }
+    }
+
+    for (path, actual) in &report.corrupt {
+        println!(
+            "  [CORRUPT] {} (content sha {})",
+            path.display(),
+            &actual[..16]
+        );
+    }
+    for (path, err) in &report.unreadable {
+        println!("  [UNREADABLE] {}: {}", path.display(), err);
+    }
+    for path in &report.misplaced {
+        println!("  [MISPLACED] {}", path.display());
+    }
+    for path in &report.stray {
+        println!("  [STRAY] {}", path.display());
+    }
+    for (kind, err) in &report.index_errors {
+        println!("  [INDEX] {}: {}", kind, err);
+    }
+    for d in &report.dangling {
+        println!(
+            "  [DANGLING] {} key={} blob={}",
+            d.kind,
+            d.input_key,
+            &d.blob_sha256[..16]
+        );
+    }
+    for d in &report.broken_refs {
+        println!(
+            "  [BAD BLOB] {} key={} blob={} (corrupt, unreadable or misplaced)",
+            d.kind,
+            d.input_key,
+            &d.blob_sha256[..16]
+        );
+    }
+    if !rebuild {
+        for b in &report.orphaned {
+            println!(
+                "  [ORPHAN] {} ({})",
+                &b.sha256[..16],
+                fmt_bytes(b.size_bytes)
+            );
+        }
+        if !report.orphaned.is_empty() {
+            println!("  Re-run with --rebuild to re-index recoverable orphans, or `recart gc` to drop them.");
+        }
+    }
+
+    if report.is_clean() {
+        println!("Store is consistent.");
+    }
+}
+
+fn sha256_file(path: &Path) -> Result<(String, u64)> {
+    let mut f =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = f
+            .read(&mut buf)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
+
+/// Hash `paths` on `workers` threads. Each file is still hashed front to back
+/// by a single thread, so digests are identical to the serial computation;
+/// results are returned in input order.
+pub(crate) fn sha256_files_parallel(
+    paths: &[PathBuf],
+    workers: usize,
+) -> Vec<Result<(String, u64)>> {
+    crate::pool::map_bounded(paths, workers, |path| sha256_file(path))
+}
+
+fn read_dir_sorted(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.sort();
+    Ok(paths)
+}
+
+/// Blob names are the lowercase `{:x}` digest.
+fn is_blob_name(s: &str) -> bool {
+    is_hex_64(s) && !s.bytes().any(|b| b.is_ascii_uppercase())
+}
+
+fn file_name_str(path: &Path) -> String {
+    path.file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Removes the directory on drop, so failed assertions don't leave it behind.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("recart-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn sha_hex(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+
+    /// Write `data` as `<blob_root>/<prefix>/<name>`.
+    fn put(blob_root: &Path, prefix: &str, name: &str, data: &[u8]) -> PathBuf {
+        let dir = blob_root.join(prefix);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn index_ref(input_key: &str, blob_sha256: &str) -> IndexRef {
+        IndexRef {
+            input_key: input_key.to_string(),
+            blob_sha256: blob_sha256.to_string(),
+        }
+    }
+
+    struct Fixture {
+        _tmp: TempDir,
+        blob_root: PathBuf,
+        index_root: PathBuf,
+        good: String,
+        orphan: String,
+        corrupt: String,
+        misplaced: String,
+    }
+
+    fn fixture(name: &str) -> Fixture {
+        let tmp = TempDir::new(name);
+        let blob_root = tmp.0.join("blobs/sha256");
+        let index_root = tmp.0.join("index");
+        std::fs::create_dir_all(index_root.join("rootfs_erofs")).unwrap();
+        std::fs::create_dir_all(index_root.join("initramfs")).unwrap();
+
+        let good = sha_hex(b"good");
+        put(&blob_root, &good[..2], &good, b"good");
+
+        let orphan = sha_hex(b"orphan");
+        put(&blob_root, &orphan[..2], &orphan, b"orphan");
+
+        let corrupt = sha_hex(b"expected");
+        put(&blob_root, &corrupt[..2], &corrupt, b"tampered");
+
+        let misplaced = sha_hex(b"misplaced");
+        let wrong_prefix = if misplaced.starts_with("00") {
+            "ff"
+        } else {
+            "00"
+        };
+        put(&blob_root, wrong_prefix, &misplaced, b"misplaced");
+
+        let upper = sha_hex(b"upper").to_ascii_uppercase();
+        put(
+            &blob_root,
+            &upper[..2].to_ascii_lowercase(),
+            &upper,
+            b"upper",
+        );
+        put(&blob_root, &good[..2], "README", b"not a blob");
+        std::fs::write(blob_root.join("stray-at-root"), b"").unwrap();
+
+        Fixture {
+            _tmp: tmp,
+            blob_root,
+            index_root,
+            good,
+            orphan,
+            corrupt,
+            misplaced,
+        }
+    }
+
+    #[test]
+    fn scan_blobs_classifies_files() {
+        let f = fixture("fsck-blobs");
+        let mut report = Report::default();
+        let scan = scan_blobs(&f.blob_root, 2, &mut report).unwrap();
+
+        let verified: Vec<&String> = scan.verified.keys().collect();
+        let mut expected = vec![&f.good, &f.orphan];
+        expected.sort();
+        assert_eq!(verified, expected);
+
+        assert_eq!(report.corrupt.len(), 1);
+        assert_eq!(file_name_str(&report.corrupt[0].0), f.corrupt);
+        assert_eq!(report.corrupt[0].1, sha_hex(b"tampered"));
+
+        assert_eq!(report.misplaced.len(), 1);
+        assert_eq!(file_name_str(&report.misplaced[0]), f.misplaced);
+
+        // Uppercase name, non-hex name, and a plain file beside the prefix dirs.
+        assert_eq!(report.stray.len(), 3);
+        assert!(report.unreadable.is_empty());
+
+        for sha in [&f.good, &f.orphan, &f.corrupt, &f.misplaced] {
+            assert!(scan.on_disk.contains(sha));
+        }
+        assert_eq!(report.blobs_checked, 3);
+    }
+
+    #[test]
+    fn scan_index_separates_dangling_bad_blob_and_malformed() {
+        let f = fixture("fsck-index");
+        let mut report = Report::default();
+        let scan = scan_blobs(&f.blob_root, 1, &mut report).unwrap();
+
+        let missing = sha_hex(b"never stored");
+        let (good, corrupt, misplaced) = (f.good.clone(), f.corrupt.clone(), f.misplaced.clone());
+        let list_kind = move |kind: &str| -> Result<Vec<IndexRef>> {
+            match kind {
+                "rootfs_erofs" => Ok(vec![
+                    index_ref("k-good", &good),
+                    index_ref("k-corrupt", &corrupt),
+                    index_ref("k-misplaced", &misplaced),
+                    index_ref("k-missing", &missing),
+                    // 15 ASCII bytes then a multi-byte char straddling byte 16.
+                    index_ref("k-malformed", "0123456789abcdeé"),
+                ]),
+                _ => anyhow::bail!("unreadable index"),
+            }
+        };
+        let referenced = scan_index(&f.index_root, list_kind, &scan, &mut report).unwrap();
+
+        assert_eq!(report.index_entries, 5);
+        assert_eq!(report.dangling.len(), 1);
+        assert_eq!(report.dangling[0].input_key, "k-missing");
+        let bad: Vec<&str> = report
+            .broken_refs
+            .iter()
+            .map(|d| d.input_key.as_str())
+            .collect();
+        assert_eq!(bad, ["k-corrupt", "k-misplaced"]);
+        // The malformed sha plus the kind whose listing failed.
+        assert_eq!(report.index_errors.len(), 2);
+
+        report.orphaned = collect_orphans(scan.verified, &referenced);
+        assert_eq!(report.orphaned.len(), 1);
+        assert_eq!(report.orphaned[0].sha256, f.orphan);
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn consistent_store_is_clean() {
+        let tmp = TempDir::new("fsck-clean");
+        let blob_root = tmp.0.join("blobs/sha256");
+        let index_root = tmp.0.join("index");
+        std::fs::create_dir_all(index_root.join("initramfs")).unwrap();
+        let sha = sha_hex(b"payload");
+        put(&blob_root, &sha[..2], &sha, b"payload");
+
+        let mut report = Report::default();
+        let scan = scan_blobs(&blob_root, 4, &mut report).unwrap();
+        let listed = sha.clone();
+        let list_kind =
+            move |_: &str| -> Result<Vec<IndexRef>> { Ok(vec![index_ref("k", &listed)]) };
+        let referenced = scan_index(&index_root, list_kind, &scan, &mut report).unwrap();
+        report.orphaned = collect_orphans(scan.verified, &referenced);
+
+        assert!(report.orphaned.is_empty());
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn parallel_hashes_match_known_digests() {
+        let tmp = TempDir::new("fsck-hash");
+        let sizes = [0usize, 1, 4096, 1024 * 1024 + 7, 3 * 1024 * 1024];
+
+        let mut expected = vec![];
+        let paths: Vec<PathBuf> = sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &len)| {
+                let path = tmp.0.join(format!("blob-{i}"));
+                let data: Vec<u8> = (0..len).map(|b| (b * 31 + i) as u8).collect();
+                expected.push((sha_hex(&data), len as u64));
+                std::fs::write(&path, data).unwrap();
+                path
+            })
+            .collect();
+
+        let parallel: Vec<(String, u64)> = sha256_files_parallel(&paths, 4)
+            .into_iter()
+            .map(|r| r.unwrap())
+            .collect();
+
+        assert_eq!(parallel, expected);
+        assert_eq!(
+            parallel[0].0,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+}
@@ -1,9 +1,13 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use distro_builder::artifact_store::ArtifactStore;
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::Instant;
 
+mod fsck;
+mod pool;
 mod server;
 
 #[derive(Parser)]
@@ -36,10 +40,31 @@ enum Command {
         keep_last: usize,
     },
 
+    /// Verify blobs against their content sha and cross-check the index.
+    ///
+    /// Reports corrupt blobs, orphaned blobs and dangling index entries. Never deletes anything;
+    /// without --rebuild nothing on disk is modified.
+    Fsck {
+        /// Re-create index entries for orphaned blobs whose input key can be recovered from
+        /// the distro output directories. Like `recart ingest`, each recovered output file is
+        /// moved into the store and replaced by a hardlink to its blob.
+        #[arg(long)]
+        rebuild: bool,
+
+        /// Blob verification threads (default: available cores)
+        #[arg(long)]
+        jobs: Option<usize>,
+    },
+
     /// Ingest existing distro build artifacts into the centralized store (no builds).
     ///
     /// This will only ingest artifacts that already exist on disk.
-    Ingest,
+    Ingest {
+        /// Source hashing threads (default: available cores). Store writes always run one at a
+        /// time.
+        #[arg(long)]
+        jobs: Option<usize>,
+    },
 
     /// Serve a local web UI for exploring outputs + store contents.
     Serve {
@@ -103,8 +128,18 @@ async fn main() -> Result<()> {
             println!("Removed {} index entry(s).", removed_idx);
             println!("Removed {} unreferenced blob(s).", removed_blobs);
         }
-        Command::Ingest => {
-            ingest_all(&repo_root, &store)?;
+        Command::Fsck { rebuild, jobs } => {
+            if !fsck::run(
+                &repo_root,
+                &store,
+                rebuild,
+                jobs.unwrap_or_else(default_jobs),
+            )? {
+                anyhow::bail!("Artifact store has inconsistencies (see summary above)");
+            }
+        }
+        Command::Ingest { jobs } => {
+            ingest_all(&repo_root, &store, jobs.unwrap_or_else(default_jobs))?;
         }
         Command::Serve {
             bind,
@@ -118,25 +153,37 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-fn ingest_all(repo_root: &Path, store: &ArtifactStore) -> Result<()> {
+/// An artifact to move into the store. All jobs are planned first, their file
+/// sources hashed on the bounded `--jobs` pool, then written to the store one
+/// at a time.
+struct IngestJob {
+    distro: &'static str,
+    kind: &'static str,
+    key: String,
+    /// Output file, or the staging dir for `kernel_payload`.
+    src: PathBuf,
+}
+
+fn ingest_all(repo_root: &Path, store: &ArtifactStore, workers: usize) -> Result<()> {
     let mut any = false;
+    let mut jobs = vec![];
 
     let leviso = repo_root.join("leviso");
     if leviso.exists() {
         any = true;
-        ingest_leviso(&leviso, store)?;
+        ingest_leviso(&leviso, store, &mut jobs)?;
     }
 
     let acorn = repo_root.join("AcornOS");
     if acorn.exists() {
         any = true;
-        ingest_acorn(&acorn, store)?;
+        ingest_acorn(&acorn, store, &mut jobs)?;
     }
 
     let iuppiter = repo_root.join("IuppiterOS");
     if iuppiter.exists() {
         any = true;
-        ingest_iuppiter(&iuppiter, store)?;
+        ingest_iuppiter(&iuppiter, store, &mut jobs)?;
     }
 
     if !any {
@@ -145,10 +192,160 @@ fn ingest_all(repo_root: &Path, store: &ArtifactStore) -> Result<()> {
         );
     }
 
+    run_ingest_jobs(store, dedup_ingest_jobs(jobs), workers);
     Ok(())
 }
 
-fn ingest_leviso(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
+/// Planning happens before any write, so the usual "already stored" check
+/// cannot catch two distros resolving to the same kind and input key.
+fn dedup_ingest_jobs(jobs: Vec<IngestJob>) -> Vec<IngestJob> {
+    let mut seen: BTreeMap<(&'static str, String), &'static str> = BTreeMap::new();
+    let mut out = vec![];
+    for job in jobs {
+        if let Some(first) = seen.get(&(job.kind, job.key.clone())) {
+            println!(
+                "  [SKIP] {} {} (same key as {})",
+                job.distro, job.kind, first
+            );
+            continue;
+        }
+        seen.insert((job.kind, job.key.clone()), job.distro);
+        out.push(job);
+    }
+    out
+}
+
+fn run_ingest_jobs(store: &ArtifactStore, jobs: Vec<IngestJob>, workers: usize) {
+    if jobs.is_empty() {
+        return;
+    }
+
+    // Hash file sources up front (read-only, safe to parallelize). This gives
+    // each source's size before it is moved, spots identical content across
+    // distros, and lets us check the sha the store reports. kernel_payload
+    // sources are staging dirs; the store archives and hashes those itself.
+    let files: Vec<PathBuf> = jobs
+        .iter()
+        .filter(|j| j.kind != "kernel_payload")
+        .map(|j| j.src.clone())
+        .collect();
+    let workers = workers.clamp(1, files.len().max(1));
+    println!(
+        "== Hashing {} source file(s) on {} thread(s) ==",
+        files.len(),
+        workers
+    );
+    let started = Instant::now();
+    let mut hashes = fsck::sha256_files_parallel(&files, workers).into_iter();
+    let hash_secs = started.elapsed().as_secs_f64();
+
+    let mut planned = vec![];
+    let mut hashed_bytes = 0u64;
+    let mut by_content: BTreeMap<String, (&'static str, &'static str)> = BTreeMap::new();
+    for job in &jobs {
+        if job.kind == "kernel_payload" {
+            planned.push((job, None));
+            continue;
+        }
+        match hashes.next().expect("one hash per file source") {
+            Ok((sha, size)) => {
+                hashed_bytes += size;
+                if let Some((distro, kind)) = by_content.get(&sha) {
+                    println!(
+                        "  [INFO] {} {} has the same content as {} {} (blob stored once)",
+                        job.distro, job.kind, distro, kind
+                    );
+                } else {
+                    by_content.insert(sha.clone(), (job.distro, job.kind));
+                }
+                planned.push((job, Some((sha, size))));
+            }
+            Err(e) => eprintln!(
+                "  [WARN] {} {} source unreadable: {:#}",
+                job.distro, job.kind, e
+            ),
+        }
+    }
+    println!(
+        "  Hashed {} in {:.1}s ({}/s)",
+        fmt_bytes(hashed_bytes),
+        hash_secs,
+        fmt_bytes(rate(hashed_bytes, hash_secs))
+    );
+
+    println!("== Ingesting {} artifact(s) ==", planned.len());
+    let started = Instant::now();
+    let mut total = 0u64;
+    for (job, hashed) in planned {
+        let meta = BTreeMap::new();
+        let result = if job.kind == "kernel_payload" {
+            store.put_kernel_payload(&job.key, &job.src, meta)
+        } else {
+            store.ingest_file_move_and_link(job.kind, &job.key, &job.src, meta)
+        };
+        let sha = match result {
+            Ok(sha) => sha,
+            Err(e) => {
+                eprintln!(
+                    "  [WARN] {} {} ingest failed: {:#}",
+                    job.distro, job.kind, e
+                );
+                continue;
+            }
+        };
+
+        let size = match hashed {
+            Some((expected, size)) => {
+                if sha != expected {
+                    eprintln!(
+                        "  [WARN] {} {} changed between hashing and ingest (hashed {}, stored {})",
+                        job.distro,
+                        job.kind,
+                        &expected[..16],
+                        &sha[..16]
+                    );
+                }
+                Some(size)
+            }
+            None => match store.get(job.kind, &job.key) {
+                Ok(stored) => stored.map(|s| s.entry.size_bytes),
+                Err(e) => {
+                    eprintln!(
+                        "  [WARN] {} {} ingested, but size lookup failed: {:#}",
+                        job.distro, job.kind, e
+                    );
+                    None
+                }
+            },
+        };
+        total += size.unwrap_or(0);
+        println!(
+            "  {:<10} {:<17} ingested blob={}  size={}",
+            job.distro,
+            job.kind,
+            &sha[..16],
+            size.map_or_else(|| "?".to_string(), fmt_bytes)
+        );
+    }
+
+    let secs = started.elapsed().as_secs_f64();
+    println!(
+        "  Ingested {} in {:.1}s ({}/s)",
+        fmt_bytes(total),
+        secs,
+        fmt_bytes(rate(total, secs))
+    );
+}
+
+fn rate(bytes: u64, secs: f64) -> u64 {
+    if secs > 0.0 {
+        (bytes as f64 / secs) as u64
+    } else {
+        bytes
+    }
+}
+
+fn ingest_leviso(base_dir: &Path, store: &ArtifactStore, jobs: &mut Vec<IngestJob>) -> Result<()> {
     println!("== Ingest leviso ==");
     let out = distro_builder::artifact_store::central_output_dir_for_distro(base_dir);
     if !out.exists() {
@@ -171,14 +368,12 @@ fn ingest_leviso(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
                 if store.get("kernel_payload", &key)?.is_some() {
                     println!("  [SKIP] kernel_payload (already stored)");
                 } else {
-                    match store.put_kernel_payload(
-                        &key,
-                        &staging,
-                        std::collections::BTreeMap::new(),
-                    ) {
-                        Ok(sha) => println!("  kernel_payload  stored blob={}", &sha[..16]),
-                        Err(e) => eprintln!("  [WARN] kernel_payload ingest failed: {:#}", e),
-                    }
+                    jobs.push(IngestJob {
+                        distro: "leviso",
+                        kind: "kernel_payload",
+                        key: key.clone(),
+                        src: staging.clone(),
+                    });
                 }
             }
             None => println!(
@@ -199,15 +394,12 @@ fn ingest_leviso(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
                 if store.get("rootfs_erofs", &key)?.is_some() {
                     println!("  [SKIP] rootfs_erofs (already stored)");
                 } else {
-                    match store.ingest_file_move_and_link(
-                        "rootfs_erofs",
-                        &key,
-                        &rootfs,
-                        std::collections::BTreeMap::new(),
-                    ) {
-                        Ok(sha) => println!("  rootfs_erofs    ingested blob={}", &sha[..16]),
-                        Err(e) => eprintln!("  [WARN] rootfs ingest failed: {:#}", e),
-                    }
+                    jobs.push(IngestJob {
+                        distro: "leviso",
+                        kind: "rootfs_erofs",
+                        key: key.clone(),
+                        src: rootfs.clone(),
+                    });
                 }
             }
             None => println!(
@@ -228,15 +420,12 @@ fn ingest_leviso(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
                 if store.get("initramfs", &key)?.is_some() {
                     println!("  [SKIP] initramfs (already stored)");
                 } else {
-                    match store.ingest_file_move_and_link(
-                        "initramfs",
-                        &key,
-                        &initramfs,
-                        std::collections::BTreeMap::new(),
-                    ) {
-                        Ok(sha) => println!("  initramfs       ingested blob={}", &sha[..16]),
-                        Err(e) => eprintln!("  [WARN] initramfs ingest failed: {:#}", e),
-                    }
+                    jobs.push(IngestJob {
+                        distro: "leviso",
+                        kind: "initramfs",
+                        key: key.clone(),
+                        src: initramfs.clone(),
+                    });
                 }
             }
             None => println!(
@@ -257,15 +446,12 @@ fn ingest_leviso(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
                 if store.get("install_initramfs", &key)?.is_some() {
                     println!("  [SKIP] install_initramfs (already stored)");
                 } else {
-                    match store.ingest_file_move_and_link(
-                        "install_initramfs",
-                        &key,
-                        &install_initramfs,
-                        std::collections::BTreeMap::new(),
-                    ) {
-                        Ok(sha) => println!("  install_initramfs ingested blob={}", &sha[..16]),
-                        Err(e) => eprintln!("  [WARN] install initramfs ingest failed: {:#}", e),
-                    }
+                    jobs.push(IngestJob {
+                        distro: "leviso",
+                        kind: "install_initramfs",
+                        key: key.clone(),
+                        src: install_initramfs.clone(),
+                    });
                 }
             }
             None => println!(
@@ -292,15 +478,12 @@ fn ingest_leviso(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
                 if store.get("iso", &key)?.is_some() {
                     println!("  [SKIP] iso (already stored)");
                 } else {
-                    match store.ingest_file_move_and_link(
-                        "iso",
-                        &key,
-                        &iso,
-                        std::collections::BTreeMap::new(),
-                    ) {
-                        Ok(sha) => println!("  iso            ingested blob={}", &sha[..16]),
-                        Err(e) => eprintln!("  [WARN] iso ingest failed: {:#}", e),
-                    }
+                    jobs.push(IngestJob {
+                        distro: "leviso",
+                        kind: "iso",
+                        key: key.clone(),
+                        src: iso.clone(),
+                    });
                 }
 
                 if let Some(checksum) = find_iso_checksum_file(&iso) {
@@ -308,17 +491,12 @@ fn ingest_leviso(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
                         if store.get("iso_checksum", &key)?.is_some() {
                             println!("  [SKIP] iso_checksum (already stored)");
                         } else {
-                            match store.ingest_file_move_and_link(
-                                "iso_checksum",
-                                &key,
-                                &checksum,
-                                std::collections::BTreeMap::new(),
-                            ) {
-                                Ok(sha) => {
-                                    println!("  iso_checksum    ingested blob={}", &sha[..16])
-                                }
-                                Err(e) => eprintln!("  [WARN] iso checksum ingest failed: {:#}", e),
-                            }
+                            jobs.push(IngestJob {
+                                distro: "leviso",
+                                kind: "iso_checksum",
+                                key: key.clone(),
+                                src: checksum.clone(),
+                            });
                         }
                     }
                 } else {
@@ -334,7 +512,7 @@ fn ingest_leviso(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
     Ok(())
 }
 
-fn ingest_acorn(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
+fn ingest_acorn(base_dir: &Path, store: &ArtifactStore, jobs: &mut Vec<IngestJob>) -> Result<()> {
     println!("== Ingest AcornOS ==");
     let out = distro_builder::artifact_store::central_output_dir_for_distro(base_dir);
     if !out.exists() {
@@ -356,14 +534,12 @@ fn ingest_acorn(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
                 if store.get("kernel_payload", &key)?.is_some() {
                     println!("  [SKIP] kernel_payload (already stored)");
                 } else {
-                    match store.put_kernel_payload(
-                        &key,
-                        &staging,
-                        std::collections::BTreeMap::new(),
-                    ) {
-                        Ok(sha) => println!("  kernel_payload  stored blob={}", &sha[..16]),
-                        Err(e) => eprintln!("  [WARN] kernel_payload ingest failed: {:#}", e),
-                    }
+                    jobs.push(IngestJob {
+                        distro: "AcornOS",
+                        kind: "kernel_payload",
+                        key: key.clone(),
+                        src: staging.clone(),
+                    });
                 }
             }
             None => println!(
@@ -384,15 +560,12 @@ fn ingest_acorn(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
                 if store.get("rootfs_erofs", &key)?.is_some() {
                     println!("  [SKIP] rootfs_erofs (already stored)");
                 } else {
-                    match store.ingest_file_move_and_link(
-                        "rootfs_erofs",
-                        &key,
-                        &rootfs,
-                        std::collections::BTreeMap::new(),
-                    ) {
-                        Ok(sha) => println!("  rootfs_erofs    ingested blob={}", &sha[..16]),
-                        Err(e) => eprintln!("  [WARN] rootfs ingest failed: {:#}", e),
-                    }
+                    jobs.push(IngestJob {
+                        distro: "AcornOS",
+                        kind: "rootfs_erofs",
+                        key: key.clone(),
+                        src: rootfs.clone(),
+                    });
                 }
             }
             None => println!(
@@ -413,15 +586,12 @@ fn ingest_acorn(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
                 if store.get("initramfs", &key)?.is_some() {
                     println!("  [SKIP] initramfs (already stored)");
                 } else {
-                    match store.ingest_file_move_and_link(
-                        "initramfs",
-                        &key,
-                        &initramfs,
-                        std::collections::BTreeMap::new(),
-                    ) {
-                        Ok(sha) => println!("  initramfs       ingested blob={}", &sha[..16]),
-                        Err(e) => eprintln!("  [WARN] initramfs ingest failed: {:#}", e),
-                    }
+                    jobs.push(IngestJob {
+                        distro: "AcornOS",
+                        kind: "initramfs",
+                        key: key.clone(),
+                        src: initramfs.clone(),
+                    });
                 }
             }
             None => println!(
@@ -447,15 +617,12 @@ fn ingest_acorn(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
                 if store.get("iso", &key)?.is_some() {
                     println!("  [SKIP] iso (already stored)");
                 } else {
-                    match store.ingest_file_move_and_link(
-                        "iso",
-                        &key,
-                        &iso,
-                        std::collections::BTreeMap::new(),
-                    ) {
-                        Ok(sha) => println!("  iso            ingested blob={}", &sha[..16]),
-                        Err(e) => eprintln!("  [WARN] iso ingest failed: {:#}", e),
-                    }
+                    jobs.push(IngestJob {
+                        distro: "AcornOS",
+                        kind: "iso",
+                        key: key.clone(),
+                        src: iso.clone(),
+                    });
                 }
 
                 if let Some(checksum) = find_iso_checksum_file(&iso) {
@@ -463,17 +630,12 @@ fn ingest_acorn(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
                         if store.get("iso_checksum", &key)?.is_some() {
                             println!("  [SKIP] iso_checksum (already stored)");
                         } else {
-                            match store.ingest_file_move_and_link(
-                                "iso_checksum",
-                                &key,
-                                &checksum,
-                                std::collections::BTreeMap::new(),
-                            ) {
-                                Ok(sha) => {
-                                    println!("  iso_checksum    ingested blob={}", &sha[..16])
-                                }
-                                Err(e) => eprintln!("  [WARN] iso checksum ingest failed: {:#}", e),
-                            }
+                            jobs.push(IngestJob {
+                                distro: "AcornOS",
+                                kind: "iso_checksum",
+                                key: key.clone(),
+                                src: checksum.clone(),
+                            });
                         }
                     }
                 } else {
@@ -489,7 +651,11 @@ fn ingest_acorn(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
     Ok(())
 }
 
-fn ingest_iuppiter(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
+fn ingest_iuppiter(
+    base_dir: &Path,
+    store: &ArtifactStore,
+    jobs: &mut Vec<IngestJob>,
+) -> Result<()> {
     println!("== Ingest IuppiterOS ==");
     let out = distro_builder::artifact_store::central_output_dir_for_distro(base_dir);
     if !out.exists() {
@@ -511,14 +677,12 @@ fn ingest_iuppiter(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
                 if store.get("kernel_payload", &key)?.is_some() {
                     println!("  [SKIP] kernel_payload (already stored)");
                 } else {
-                    match store.put_kernel_payload(
-                        &key,
-                        &staging,
-                        std::collections::BTreeMap::new(),
-                    ) {
-                        Ok(sha) => println!("  kernel_payload  stored blob={}", &sha[..16]),
-                        Err(e) => eprintln!("  [WARN] kernel_payload ingest failed: {:#}", e),
-                    }
+                    jobs.push(IngestJob {
+                        distro: "IuppiterOS",
+                        kind: "kernel_payload",
+                        key: key.clone(),
+                        src: staging.clone(),
+                    });
                 }
             }
             None => println!(
@@ -539,15 +703,12 @@ fn ingest_iuppiter(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
                 if store.get("rootfs_erofs", &key)?.is_some() {
                     println!("  [SKIP] rootfs_erofs (already stored)");
                 } else {
-                    match store.ingest_file_move_and_link(
-                        "rootfs_erofs",
-                        &key,
-                        &rootfs,
-                        std::collections::BTreeMap::new(),
-                    ) {
-                        Ok(sha) => println!("  rootfs_erofs    ingested blob={}", &sha[..16]),
-                        Err(e) => eprintln!("  [WARN] rootfs ingest failed: {:#}", e),
-                    }
+                    jobs.push(IngestJob {
+                        distro: "IuppiterOS",
+                        kind: "rootfs_erofs",
+                        key: key.clone(),
+                        src: rootfs.clone(),
+                    });
                 }
             }
             None => println!(
@@ -568,15 +729,12 @@ fn ingest_iuppiter(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
                 if store.get("initramfs", &key)?.is_some() {
                     println!("  [SKIP] initramfs (already stored)");
                 } else {
-                    match store.ingest_file_move_and_link(
-                        "initramfs",
-                        &key,
-                        &initramfs,
-                        std::collections::BTreeMap::new(),
-                    ) {
-                        Ok(sha) => println!("  initramfs       ingested blob={}", &sha[..16]),
-                        Err(e) => eprintln!("  [WARN] initramfs ingest failed: {:#}", e),
-                    }
+                    jobs.push(IngestJob {
+                        distro: "IuppiterOS",
+                        kind: "initramfs",
+                        key: key.clone(),
+                        src: initramfs.clone(),
+                    });
                 }
             }
             None => println!(
@@ -602,15 +760,12 @@ fn ingest_iuppiter(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
                 if store.get("iso", &key)?.is_some() {
                     println!("  [SKIP] iso (already stored)");
                 } else {
-                    match store.ingest_file_move_and_link(
-                        "iso",
-                        &key,
-                        &iso,
-                        std::collections::BTreeMap::new(),
-                    ) {
-                        Ok(sha) => println!("  iso            ingested blob={}", &sha[..16]),
-                        Err(e) => eprintln!("  [WARN] iso ingest failed: {:#}", e),
-                    }
+                    jobs.push(IngestJob {
+                        distro: "IuppiterOS",
+                        kind: "iso",
+                        key: key.clone(),
+                        src: iso.clone(),
+                    });
                 }
 
                 if let Some(checksum) = find_iso_checksum_file(&iso) {
@@ -618,17 +773,12 @@ fn ingest_iuppiter(base_dir: &Path, store: &ArtifactStore) -> Result<()> {
                         if store.get("iso_checksum", &key)?.is_some() {
                             println!("  [SKIP] iso_checksum (already stored)");
                         } else {
-                            match store.ingest_file_move_and_link(
-                                "iso_checksum",
-                                &key,
-                                &checksum,
-                                std::collections::BTreeMap::new(),
-                            ) {
-                                Ok(sha) => {
-                                    println!("  iso_checksum    ingested blob={}", &sha[..16])
-                                }
-                                Err(e) => eprintln!("  [WARN] iso checksum ingest failed: {:#}", e),
-                            }
+                            jobs.push(IngestJob {
+                                distro: "IuppiterOS",
+                                kind: "iso_checksum",
+                                key: key.clone(),
+                                src: checksum.clone(),
+                            });
                         }
                     }
                 } else {
@@ -665,6 +815,10 @@ fn find_iso_checksum_file(iso_path: &Path) -> Option<PathBuf> {
     None
 }
 
+fn default_jobs() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
 fn find_repo_root(start: PathBuf) -> Result<PathBuf> {
     let mut cur = start;
     loop {
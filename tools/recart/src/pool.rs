@@ -0,0 +1,72 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Apply `f` to every item on `workers` threads pulling from a shared cursor.
+/// Results are returned in input order.
+pub fn map_bounded<T, R, F>(items: &[T], workers: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<Option<R>> = items.iter().map(|_| None).collect();
+
+    std::thread::scope(|s| {
+        let handles: Vec<_> = (0..workers.max(1))
+            .map(|_| {
+                s.spawn(|| {
+                    let mut done = vec![];
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(i) else {
+                            break;
+                        };
+                        done.push((i, f(item)));
+                    }
+                    done
+                })
+            })
+            .collect();
+
+        for h in handles {
+            for (i, r) in h.join().expect("worker thread panicked") {
+                results[i] = Some(r);
+            }
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|r| r.expect("every item is processed exactly once"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn zero_workers_still_processes_every_item() {
+        let items: Vec<u32> = (0..10).collect();
+        let doubled: Vec<u32> = items.iter().map(|x| x * 2).collect();
+        assert_eq!(map_bounded(&items, 0, |x| x * 2), doubled);
+    }
+
+    #[test]
+    fn more_workers_than_items_keeps_input_order() {
+        let items: Vec<u64> = (0..4).collect();
+        // Earlier items finish last, so completion order is reversed.
+        let out = map_bounded(&items, 16, |&x| {
+            std::thread::sleep(Duration::from_millis((4 - x) * 10));
+            x + 100
+        });
+        assert_eq!(out, [100, 101, 102, 103]);
+    }
+
+    #[test]
+    fn empty_input_yields_empty_output() {
+        let items: Vec<u8> = vec![];
+        assert!(map_bounded(&items, 4, |&x| x).is_empty());
+    }
+}
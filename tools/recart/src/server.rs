@@ -1295,7 +1295,7 @@ fn restore_one_kind(
     }
 }
 
-fn iso_key_files(distro_dir: &str, out_dir: &Path) -> Vec<PathBuf> {
+pub(crate) fn iso_key_files(distro_dir: &str, out_dir: &Path) -> Vec<PathBuf> {
     match distro_dir {
         "leviso" => vec![
             out_dir.join(".kernel-inputs.hash"),
@@ -1312,7 +1312,7 @@ fn iso_key_files(distro_dir: &str, out_dir: &Path) -> Vec<PathBuf> {
     }
 }
 
-fn distro_rootfs_name(distro_dir: &str) -> &'static str {
+pub(crate) fn distro_rootfs_name(distro_dir: &str) -> &'static str {
     match distro_dir {
         "leviso" => distro_spec::levitate::ROOTFS_NAME,
         "AcornOS" => distro_spec::acorn::ROOTFS_NAME,
@@ -1321,7 +1321,7 @@ fn distro_rootfs_name(distro_dir: &str) -> &'static str {
     }
 }
 
-fn distro_initramfs_name(distro_dir: &str) -> &'static str {
+pub(crate) fn distro_initramfs_name(distro_dir: &str) -> &'static str {
     match distro_dir {
         "leviso" => distro_spec::levitate::INITRAMFS_LIVE_OUTPUT,
         "AcornOS" => distro_spec::acorn::INITRAMFS_LIVE_OUTPUT,
@@ -1330,7 +1330,7 @@ fn distro_initramfs_name(distro_dir: &str) -> &'static str {
     }
 }
 
-fn distro_iso_name(distro_dir: &str) -> &'static str {
+pub(crate) fn distro_iso_name(distro_dir: &str) -> &'static str {
     match distro_dir {
         "leviso" => distro_spec::levitate::ISO_FILENAME,
         "AcornOS" => distro_spec::acorn::ISO_FILENAME,
@@ -1339,15 +1339,19 @@ fn distro_iso_name(distro_dir: &str) -> &'static str {
     }
 }
 
+pub(crate) fn is_hex_64(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
 fn validate_hex_64(s: &str) -> Result<(), ApiError> {
-    if s.len() != 64 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+    if !is_hex_64(s) {
         return Err(ApiError::BadRequest("expected 64 hex chars".to_string()));
     }
     Ok(())
 }
 
 #[cfg(unix)]
-fn hardlink_info(out_file: &Path, blob_file: &Path) -> Option<(bool, u64)> {
+pub(crate) fn hardlink_info(out_file: &Path, blob_file: &Path) -> Option<(bool, u64)> {
     use std::os::unix::fs::MetadataExt;
     let out_md = std::fs::metadata(out_file).ok()?;
     let blob_md = std::fs::metadata(blob_file).ok()?;
@@ -1356,7 +1360,7 @@ fn hardlink_info(out_file: &Path, blob_file: &Path) -> Option<(bool, u64)> {
 }
 
 #[cfg(not(unix))]
-fn hardlink_info(_out_file: &Path, _blob_file: &Path) -> Option<(bool, u64)> {
+pub(crate) fn hardlink_info(_out_file: &Path, _blob_file: &Path) -> Option<(bool, u64)> {
     None
 }
 